@@ -0,0 +1,155 @@
+//! Transparent per-frame compression, keyed on the [`Codec`] configured via
+//! [`Decoder::with_codec`](crate::Decoder::with_codec) and
+//! [`EncoderWriter::with_codec`](crate::EncoderWriter::with_codec).
+//!
+//! Compression is orthogonal to the Frame Streams framing itself: a data
+//! frame's declared length covers the *compressed* bytes, which are
+//! inflated (or deflated, on the write side) before a caller ever sees
+//! them.
+
+use crc32fast::Hasher as Crc32;
+use flate2::read::{GzDecoder, GzEncoder};
+use flate2::Compression;
+use std::io::Read;
+
+/// The compression applied to each data frame's payload, independent of
+/// the Frame Streams framing itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Codec {
+    /// Frames are stored uncompressed (the default).
+    #[default]
+    Identity,
+    /// Frames are individually gzip-compressed, via [`flate2`].
+    Gzip,
+    /// Frames are individually LZ4-block-compressed. Each compressed
+    /// payload is `[4-byte LE decompressed size][lz4 block][4-byte BE
+    /// CRC32 of the size and block]`, so the decoder can size its
+    /// output buffer up front and detect a corrupted block before
+    /// handing back truncated data.
+    Lz4,
+}
+
+/// A data frame's payload failed to decompress under its configured
+/// [`Codec`] — a corrupt frame, a truncated block, or a checksum mismatch.
+#[derive(Debug)]
+pub(crate) struct CodecError;
+
+impl Codec {
+    /// Inflate a data frame's raw payload, as read off the wire, rejecting
+    /// output over `max_len` bytes.
+    ///
+    /// A compressed frame's *wire* length is bounded by
+    /// [`Decoder::max_frame_length`](crate::Decoder::max_frame_length)
+    /// before it ever reaches here, but its *decompressed* size is
+    /// attacker-controlled independent of that: a tiny gzip or LZ4 payload
+    /// can claim an enormous uncompressed size. `max_len` closes that hole
+    /// by capping decompression output to the same bound.
+    pub(crate) fn decompress(
+        self,
+        buf: &[u8],
+        max_len: usize,
+    ) -> std::result::Result<Vec<u8>, CodecError> {
+        match self {
+            Codec::Identity => Ok(buf.to_vec()),
+            Codec::Gzip => {
+                let mut out = Vec::new();
+                GzDecoder::new(buf)
+                    .take(max_len as u64 + 1)
+                    .read_to_end(&mut out)
+                    .map_err(|_| CodecError)?;
+                if out.len() > max_len {
+                    return Err(CodecError);
+                }
+                Ok(out)
+            }
+            Codec::Lz4 => decompress_lz4(buf, max_len),
+        }
+    }
+
+    /// Deflate a payload before it is written as a data frame.
+    pub(crate) fn compress(self, buf: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Codec::Identity => Ok(buf.to_vec()),
+            Codec::Gzip => {
+                let mut out = Vec::new();
+                GzEncoder::new(buf, Compression::default()).read_to_end(&mut out)?;
+                Ok(out)
+            }
+            Codec::Lz4 => Ok(compress_lz4(buf)),
+        }
+    }
+}
+
+const LZ4_TRAILER_LEN: usize = 4;
+
+fn compress_lz4(buf: &[u8]) -> Vec<u8> {
+    let block = lz4_flex::block::compress_prepend_size(buf);
+    let mut crc = Crc32::new();
+    crc.update(&block);
+    let mut out = block;
+    out.extend_from_slice(&crc.finalize().to_be_bytes());
+    out
+}
+
+fn decompress_lz4(buf: &[u8], max_len: usize) -> std::result::Result<Vec<u8>, CodecError> {
+    if buf.len() < LZ4_TRAILER_LEN {
+        return Err(CodecError);
+    }
+    let (block, trailer) = buf.split_at(buf.len() - LZ4_TRAILER_LEN);
+    let want_crc = u32::from_be_bytes(trailer.try_into().unwrap());
+
+    let mut crc = Crc32::new();
+    crc.update(block);
+    if crc.finalize() != want_crc {
+        return Err(CodecError);
+    }
+
+    // The decompressed size is a 4-byte prefix on `block`, attacker
+    // controlled independent of the CRC above. Reject it before
+    // `lz4_flex` allocates a buffer of that size.
+    let (uncompressed_size, block) =
+        lz4_flex::block::uncompressed_size(block).map_err(|_| CodecError)?;
+    if uncompressed_size > max_len {
+        return Err(CodecError);
+    }
+    lz4_flex::block::decompress(block, uncompressed_size).map_err(|_| CodecError)
+}
+
+#[test]
+fn gzip_round_trip() {
+    let data = b"the quick brown fox jumps over the lazy dog".repeat(4);
+    let compressed = Codec::Gzip.compress(&data).unwrap();
+    assert_eq!(Codec::Gzip.decompress(&compressed, data.len()).unwrap(), data);
+}
+
+#[test]
+fn lz4_round_trip() {
+    let data = b"the quick brown fox jumps over the lazy dog".repeat(4);
+    let compressed = Codec::Lz4.compress(&data).unwrap();
+    assert_eq!(Codec::Lz4.decompress(&compressed, data.len()).unwrap(), data);
+}
+
+#[test]
+fn lz4_rejects_corrupted_block() {
+    let compressed = Codec::Lz4.compress(b"test-content").unwrap();
+    let mut corrupted = compressed;
+    // Flip the first byte of the LZ4 block, just past its 4-byte size
+    // prefix.
+    corrupted[4] ^= 0xff;
+    assert!(Codec::Lz4.decompress(&corrupted, 1024).is_err());
+}
+
+#[test]
+fn gzip_rejects_decompressed_output_over_max_len() {
+    // A small compressed payload that expands well past the cap.
+    let data = vec![0u8; 1 << 20];
+    let compressed = Codec::Gzip.compress(&data).unwrap();
+    assert!(Codec::Gzip.decompress(&compressed, 1024).is_err());
+}
+
+#[test]
+fn lz4_rejects_declared_size_over_max_len() {
+    let data = vec![0u8; 1 << 20];
+    let compressed = Codec::Lz4.compress(&data).unwrap();
+    assert!(Codec::Lz4.decompress(&compressed, 1024).is_err());
+}
@@ -0,0 +1,36 @@
+//! Control frame type and field constants used by the Frame Streams
+//! protocol.
+//!
+//! See the [Frame Streams specification][spec] for the full list of
+//! control frame types and fields.
+//!
+//! [spec]: https://github.com/farsightsec/fstrm/blob/master/fstrm/control.h
+
+/// Accepts one or more of the content types offered in a `CONTROL_READY`
+/// frame.
+///
+/// Only used by the bidirectional handshake, which requires `std`.
+#[cfg(not(feature = "no_std"))]
+pub(crate) const CONTROL_ACCEPT: u32 = 0x01;
+
+/// Begins a sequence of data frames.
+pub(crate) const CONTROL_START: u32 = 0x02;
+
+/// Ends a sequence of data frames.
+pub(crate) const CONTROL_STOP: u32 = 0x03;
+
+/// Offers one or more content types to a peer, beginning a bidirectional
+/// handshake.
+///
+/// Only used by the bidirectional handshake, which requires `std`.
+#[cfg(not(feature = "no_std"))]
+pub(crate) const CONTROL_READY: u32 = 0x04;
+
+/// Acknowledges a `CONTROL_STOP` frame, ending a bidirectional session.
+///
+/// Only used by the bidirectional handshake, which requires `std`.
+#[cfg(not(feature = "no_std"))]
+pub(crate) const CONTROL_FINISH: u32 = 0x05;
+
+/// A control field carrying a content type string.
+pub(crate) const CONTROL_FIELD_CONTENT_TYPE: u32 = 0x01;
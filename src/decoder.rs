@@ -1,27 +1,84 @@
 use crate::constants::{CONTROL_FIELD_CONTENT_TYPE, CONTROL_START, CONTROL_STOP};
-use byteorder::{BigEndian, ReadBytesExt};
-use std::{
-    io::{Error, ErrorKind, Read, Result},
-    iter::Iterator,
-};
-
-const MAX_CONTROL_FRAME_LENGTH: usize = 512;
-
-#[derive(Clone, Debug)]
-pub struct Decoder<R: Read> {
+#[cfg(not(feature = "no_std"))]
+use crate::constants::CONTROL_READY;
+#[cfg(not(feature = "no_std"))]
+use crate::codec::Codec;
+#[cfg(not(feature = "no_std"))]
+use crate::encoder::EncoderWriter;
+use crate::error::{ErrorKind, FramestreamError};
+use crate::io::ByteSource;
+#[cfg(feature = "no_std")]
+use alloc::{borrow::ToOwned, string::String, vec, vec::Vec};
+#[cfg(not(feature = "no_std"))]
+use std::io::Write;
+
+/// The result of a fallible `Decoder` operation.
+type Result<T> = core::result::Result<T, FramestreamError>;
+
+pub(crate) const MAX_CONTROL_FRAME_LENGTH: usize = 512;
+
+/// The default upper bound on a data frame's declared length, used unless
+/// overridden with [`Decoder::max_frame_length`].
+const DEFAULT_MAX_FRAME_LENGTH: usize = 1 << 20; // 1 MiB
+
+/// The size of the chunks a data frame's payload is read into, so a lying
+/// length prefix cannot force one large up-front allocation.
+const READ_CHUNK_SIZE: usize = 8 * 1024;
+
+/// No longer `Clone` as of the `bidirectional` handshake support: the
+/// `EncoderWriter<Box<dyn Write>>` it holds wraps a trait object, which
+/// cannot be cloned in general.
+pub struct Decoder<R: ByteSource> {
     reader: R,
-    //bidirectional: Option<EncoderWriter>,
+    #[cfg(not(feature = "no_std"))]
+    bidirectional: Option<EncoderWriter<Box<dyn Write>>>,
+    #[cfg(not(feature = "no_std"))]
+    codec: Codec,
     content_type: Option<String>,
+    /// The content type the stream's own `CONTROL_START` declared, if it
+    /// declared exactly one. Populated by [`Decoder::read_start_frame`]
+    /// and used by [`Decoder::negotiated_content_type`] as a fallback when
+    /// no filter was configured via [`Decoder::content_type`].
+    declared_content_type: Option<String>,
+    max_frame_length: usize,
+    /// The number of bytes consumed from `reader` so far, or, after
+    /// [`Decoder::seek_to`], the offset sought to plus the bytes consumed
+    /// since. Reported on errors so callers can tell where in the stream
+    /// decoding failed.
+    pos: u64,
     started: bool,
 }
 
-impl<R: Read> Decoder<R> {
+impl<R: ByteSource + core::fmt::Debug> core::fmt::Debug for Decoder<R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut s = f.debug_struct("Decoder");
+        s.field("reader", &self.reader);
+        #[cfg(not(feature = "no_std"))]
+        s.field("bidirectional", &self.bidirectional.is_some());
+        #[cfg(not(feature = "no_std"))]
+        s.field("codec", &self.codec);
+        s.field("content_type", &self.content_type)
+            .field("declared_content_type", &self.declared_content_type)
+            .field("max_frame_length", &self.max_frame_length)
+            .field("pos", &self.pos)
+            .field("started", &self.started)
+            .finish()
+    }
+}
+
+impl<R: ByteSource> Decoder<R> {
     /// Instantiate a new Decoder that can read from the given `source`.
     pub fn new(source: R) -> Self {
         Self {
             reader: source,
-            // bidirectional: false,
+            #[cfg(not(feature = "no_std"))]
+            bidirectional: None,
+            #[cfg(not(feature = "no_std"))]
+            codec: Codec::default(),
             content_type: None,
+            declared_content_type: None,
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+            pos: 0,
             started: false,
         }
     }
@@ -32,28 +89,191 @@ impl<R: Read> Decoder<R> {
         self.content_type = Some(ctype.to_owned());
     }
 
+    /// Set the maximum allowed length, in bytes, of a data frame's declared
+    /// length. Defaults to [`DEFAULT_MAX_FRAME_LENGTH`].
+    ///
+    /// A frame whose declared length exceeds `max` is rejected before any of
+    /// its payload is read, so a dishonest length prefix cannot be used to
+    /// force an unbounded allocation.
+    pub fn max_frame_length(&mut self, max: usize) {
+        self.max_frame_length = max;
+    }
+
+    /// The content type frames should be interpreted under: the filter
+    /// configured via [`Decoder::content_type`] if one was set, falling
+    /// back to whatever the stream's own `CONTROL_START` declared
+    /// otherwise.
+    ///
+    /// Only used by [`TypedDecoder`](crate::TypedDecoder), which requires
+    /// `std`.
+    #[cfg(not(feature = "no_std"))]
+    pub(crate) fn negotiated_content_type(&self) -> Option<&str> {
+        self.content_type
+            .as_deref()
+            .or(self.declared_content_type.as_deref())
+    }
+
+    /// The number of bytes consumed from the underlying reader so far, or,
+    /// after [`Decoder::seek_to`], the offset sought to plus the bytes
+    /// consumed since.
+    #[cfg(not(feature = "no_std"))]
+    pub(crate) fn pos(&self) -> u64 {
+        self.pos
+    }
+
+    /// Whether this decoder has completed the handshake appropriate to it
+    /// (see [`Decoder::ensure_started`]) and is reading frames from within
+    /// a segment.
+    #[cfg(not(feature = "no_std"))]
+    pub(crate) fn is_started(&self) -> bool {
+        self.started
+    }
+
+    /// Forget that this decoder has completed its handshake, so the next
+    /// frame read re-attempts [`Decoder::ensure_started`]. Used by
+    /// [`SeekableDecoder`](crate::SeekableDecoder) to resume into a new
+    /// segment after a `CONTROL_STOP`, or after seeking.
+    #[cfg(not(feature = "no_std"))]
+    pub(crate) fn reset_started(&mut self) {
+        self.started = false;
+    }
+
+    /// A mutable reference to the underlying reader, for
+    /// [`SeekableDecoder`](crate::SeekableDecoder) to seek and scan
+    /// directly.
+    #[cfg(not(feature = "no_std"))]
+    pub(crate) fn reader_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Seek the underlying reader to `pos`, resetting this decoder's
+    /// position tracking to match and marking it as started or not
+    /// according to `started` — `false` if `pos` lands on a
+    /// `CONTROL_START` frame that still needs to be read, `true` if it
+    /// lands on a frame within an already-started segment.
+    #[cfg(not(feature = "no_std"))]
+    pub(crate) fn seek_to(&mut self, pos: u64, started: bool) -> Result<()>
+    where
+        R: std::io::Seek,
+    {
+        self.reader
+            .seek(std::io::SeekFrom::Start(pos))
+            .map_err(|e| FramestreamError::io(e, self.pos))?;
+        self.pos = pos;
+        self.started = started;
+        Ok(())
+    }
+
     /// Enable bidirectional mode for this decoder, by providing an
-    /// `EncoderWriter`.
-    // pub fn bidirectional(&mut self, enc: EncoderWriter) {
-    //     self.bidirectional = Some(enc);
-    // }
+    /// `EncoderWriter` that writes back to the peer this decoder is reading
+    /// from.
+    ///
+    /// In bidirectional mode, the decoder performs the Frame Streams
+    /// handshake (`CONTROL_READY`/`CONTROL_ACCEPT`/`CONTROL_START`) before
+    /// yielding any data frames, and writes a `CONTROL_FINISH` frame once
+    /// the peer sends `CONTROL_STOP`.
+    ///
+    /// Unavailable when built with the `no_std` feature, since it requires
+    /// `std::io::Write`.
+    #[cfg(not(feature = "no_std"))]
+    pub fn bidirectional<W: Write + 'static>(&mut self, enc: W) {
+        self.bidirectional = Some(EncoderWriter::new(Box::new(enc)));
+    }
+
+    /// Transparently decompress each data frame's payload under `codec`
+    /// before it is returned. Defaults to [`Codec::Identity`].
+    ///
+    /// Unavailable when built with the `no_std` feature.
+    #[cfg(not(feature = "no_std"))]
+    pub fn with_codec(&mut self, codec: Codec) {
+        self.codec = codec;
+    }
+
+    /// Perform the handshake appropriate for this decoder before its first
+    /// frame is read: the bidirectional `CONTROL_READY`/`CONTROL_ACCEPT`
+    /// exchange when [`Decoder::bidirectional`] was used, or a plain
+    /// `CONTROL_START` read otherwise.
+    fn ensure_started(&mut self) -> Result<()> {
+        if self.started {
+            return Ok(());
+        }
+        #[cfg(not(feature = "no_std"))]
+        {
+            if self.bidirectional.is_some() {
+                self.perform_handshake()?;
+            } else {
+                self.read_start_frame()?;
+            }
+        }
+        #[cfg(feature = "no_std")]
+        {
+            self.read_start_frame()?;
+        }
+        self.started = true;
+        Ok(())
+    }
+
+    /// Write a `CONTROL_FINISH` frame if this decoder is in bidirectional
+    /// mode; a no-op otherwise, and always a no-op when built with the
+    /// `no_std` feature.
+    #[cfg(not(feature = "no_std"))]
+    fn finish_if_bidirectional(&mut self) -> Result<()> {
+        if let Some(ref mut encoder) = self.bidirectional {
+            encoder
+                .write_finish()
+                .map_err(|e| FramestreamError::io(e, self.pos))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "no_std")]
+    fn finish_if_bidirectional(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Read exactly `buf.len()` bytes from `self.reader`, tracking
+    /// `self.pos` and distinguishing a clean end-of-stream (nothing read
+    /// yet) from a truncated one (read stopped partway through).
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        let mut read = 0;
+        while read < buf.len() {
+            match self.reader.read(&mut buf[read..]) {
+                Ok(0) if read == 0 => return Err(FramestreamError::new(ErrorKind::Eof, self.pos)),
+                Ok(0) => return Err(FramestreamError::new(ErrorKind::UnexpectedEof, self.pos)),
+                Ok(n) => {
+                    read += n;
+                    self.pos += n as u64;
+                }
+                #[cfg(not(feature = "no_std"))]
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(FramestreamError::io(e, self.pos)),
+            }
+        }
+        Ok(())
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let mut buf = [0u8; 4];
+        self.read_exact(&mut buf)?;
+        Ok(u32::from_be_bytes(buf))
+    }
 
     fn read_control_frame(&mut self) -> Result<ControlFrame> {
-        let frame_len = self.reader.read_u32::<BigEndian>()? as usize;
+        let frame_len = self.read_u32()? as usize;
         if frame_len > MAX_CONTROL_FRAME_LENGTH {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("control frame too large: len={}", frame_len),
+            return Err(FramestreamError::new(
+                ErrorKind::ControlFrameTooLarge { len: frame_len },
+                self.pos,
             ));
         } else if frame_len < 4 {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "control frame too short",
+            return Err(FramestreamError::new(
+                ErrorKind::ControlFrameTooShort { len: frame_len },
+                self.pos,
             ));
         }
 
         // Read the frame's control type.
-        let control_type = self.reader.read_u32::<BigEndian>()?;
+        let control_type = self.read_u32()?;
 
         // Read the remainder of the buffer.
         let mut content_types = Vec::new();
@@ -73,203 +293,430 @@ impl<R: Read> Decoder<R> {
     /// Read a control field from `self.reader`, ensuring the field's size is
     /// less-than-or-equal-to `limit`.
     fn read_control_field(&mut self, limit: usize) -> Result<(String, usize)> {
-        let field_type = self.reader.read_u32::<BigEndian>()?;
+        let field_type = self.read_u32()?;
         if field_type != CONTROL_FIELD_CONTENT_TYPE {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                format!("expected control field content type, got {:x}", field_type),
+            return Err(FramestreamError::new(
+                ErrorKind::BadContentTypeField { got: field_type },
+                self.pos,
             ));
         }
 
-        let field_len = self.reader.read_u32::<BigEndian>()? as usize;
-        dbg!(field_len, limit);
+        let field_len = self.read_u32()? as usize;
         if field_len > limit {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                format!(
-                    "field contents too large (len={} limit={})",
-                    field_len, limit
-                ),
+            return Err(FramestreamError::new(
+                ErrorKind::ContentTypeFieldTooLarge {
+                    len: field_len,
+                    limit,
+                },
+                self.pos,
             ));
         }
 
-        let mut buf = Vec::with_capacity(field_len);
-        buf.resize(field_len, 0);
-        self.reader.read_exact(&mut buf)?;
+        let mut buf = vec![0; field_len];
+        self.read_exact(&mut buf)?;
 
-        let content_type = String::from_utf8_lossy(buf.as_slice()).into_owned();
+        let content_type = String::from_utf8_lossy(&buf).into_owned();
         let bytes_read = 8 + field_len;
 
         Ok((content_type, bytes_read))
     }
 
     fn read_start_frame(&mut self) -> Result<()> {
-        // Make sure the next four bytes are 0.
-        let n = self.reader.read_u32::<BigEndian>()?;
+        // Make sure the next four bytes are the escape sequence.
+        let n = self.read_u32()?;
         if n != 0 {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "control start frame did not start with zero",
+            return Err(FramestreamError::new(
+                ErrorKind::MissingEscape { got: n },
+                self.pos,
             ));
         }
 
         let frame = self.read_control_frame()?;
         if frame.control_type == CONTROL_START {
+            self.declared_content_type =
+                frame.content_types.unwrap_or_default().into_iter().next();
             Ok(())
         } else {
-            Err(Error::new(
-                ErrorKind::InvalidInput,
-                "expected control start frame",
+            Err(FramestreamError::new(
+                ErrorKind::UnexpectedControlType {
+                    expected: CONTROL_START,
+                    got: frame.control_type,
+                },
+                self.pos,
             ))
         }
     }
 
-    fn read_frame_length(&mut self) -> Result<usize> {
-        let n = self.reader.read_u32::<BigEndian>()?;
-        Ok(n as usize)
+    /// Read a `CONTROL_READY` frame, returning the content types the peer
+    /// offered.
+    #[cfg(not(feature = "no_std"))]
+    fn read_ready_frame(&mut self) -> Result<Vec<String>> {
+        let n = self.read_u32()?;
+        if n != 0 {
+            return Err(FramestreamError::new(
+                ErrorKind::MissingEscape { got: n },
+                self.pos,
+            ));
+        }
+
+        let frame = self.read_control_frame()?;
+        if frame.control_type != CONTROL_READY {
+            return Err(FramestreamError::new(
+                ErrorKind::UnexpectedControlType {
+                    expected: CONTROL_READY,
+                    got: frame.control_type,
+                },
+                self.pos,
+            ));
+        }
+
+        Ok(frame.content_types.unwrap_or_default())
     }
 
-    fn read_n(&mut self, n: usize, buf: &mut [u8]) -> Result<usize> {
-        if n > buf.len() {
-            Err(Error::new(
-                ErrorKind::Other,
-                "data frame too large for buffer",
-            ))
-        } else {
-            match self.reader.read_exact(&mut buf[..n]) {
-                Ok(_) => Ok(n),
-                Err(e) => Err(e),
-            }
+    /// Perform the bidirectional Frame Streams handshake: read the peer's
+    /// `CONTROL_READY`, write back a `CONTROL_ACCEPT` naming the content
+    /// types we will accept (intersected against `self.content_type`, if
+    /// set), then read the peer's `CONTROL_START`.
+    #[cfg(not(feature = "no_std"))]
+    fn perform_handshake(&mut self) -> Result<()> {
+        let offered = self.read_ready_frame()?;
+        let accepted: Vec<String> = match &self.content_type {
+            Some(ctype) => offered.into_iter().filter(|o| o == ctype).collect(),
+            None => offered,
+        };
+
+        if let Some(ref mut enc) = self.bidirectional {
+            let accepted: Vec<&str> = accepted.iter().map(String::as_str).collect();
+            enc.write_accept(accepted)
+                .map_err(|e| FramestreamError::io(e, self.pos))?;
         }
+
+        self.read_start_frame()
     }
-}
 
-struct ControlFrame {
-    control_type: u32,
+    fn read_frame_length(&mut self) -> Result<usize> {
+        Ok(self.read_u32()? as usize)
+    }
 
-    #[allow(dead_code)]
-    content_types: Option<Vec<String>>,
-}
+    /// Read a data frame's `frame_len`-byte payload, rejecting declared
+    /// lengths over `self.max_frame_length` and growing the returned buffer
+    /// in `READ_CHUNK_SIZE` chunks rather than reserving `frame_len` bytes
+    /// up front, so a forged length prefix cannot force a single large
+    /// allocation before any data has actually arrived. When a codec other
+    /// than [`Codec::Identity`] is configured, the payload is decompressed
+    /// before being returned.
+    fn read_data_frame(&mut self, frame_len: usize) -> Result<Vec<u8>> {
+        if frame_len > self.max_frame_length {
+            return Err(FramestreamError::new(
+                ErrorKind::FrameTooLarge {
+                    len: frame_len,
+                    max: self.max_frame_length,
+                },
+                self.pos,
+            ));
+        }
 
-impl<R: Read> Read for Decoder<R> {
-    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
-        // If we have not read the CONTROL_START frame yet, read it now.
-        if !self.started {
-            self.read_start_frame()?;
-            self.started = true;
+        let mut buf = Vec::with_capacity(frame_len.min(READ_CHUNK_SIZE));
+        let mut remaining = frame_len;
+        while remaining > 0 {
+            let chunk_len = remaining.min(READ_CHUNK_SIZE);
+            let start = buf.len();
+            buf.resize(start + chunk_len, 0);
+            self.read_exact(&mut buf[start..])?;
+            remaining -= chunk_len;
         }
 
-        // Read the frame length.
+        #[cfg(not(feature = "no_std"))]
+        let buf = self
+            .codec
+            .decompress(&buf, self.max_frame_length)
+            .map_err(|_| FramestreamError::new(ErrorKind::BadCodecFrame, self.pos))?;
+
+        Ok(buf)
+    }
+
+    /// Like [`Decoder::try_next`], but copies the decoded payload into a
+    /// caller-provided buffer instead of allocating a [`Frame`], for use by
+    /// the [`std::io::Read`] impl.
+    #[cfg(not(feature = "no_std"))]
+    fn read_data_frame_inner(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.ensure_started()?;
+
         let frame_len = self.read_frame_length()?;
         if frame_len == 0 {
             // This is a control frame.
             let frame = self.read_control_frame()?;
-            if frame.control_type == CONTROL_STOP {
-                // if let Some(ref mut encoder) = self.bidirectional {
-                //     // TODO: Write a CONTROL_FINISH frame.
-                // }
+            if frame.control_type != CONTROL_STOP {
+                return Err(FramestreamError::new(
+                    ErrorKind::UnexpectedControlType {
+                        expected: CONTROL_STOP,
+                        got: frame.control_type,
+                    },
+                    self.pos,
+                ));
             }
+            self.finish_if_bidirectional()?;
             return Ok(0);
         }
 
-        // Read the data into the buffer.
-        self.read_n(frame_len, &mut buf[..])
+        let data = self.read_data_frame(frame_len)?;
+        if data.len() > buf.len() {
+            return Err(FramestreamError::new(
+                ErrorKind::BufferTooSmall {
+                    frame_len: data.len(),
+                    buf_len: buf.len(),
+                },
+                self.pos,
+            ));
+        }
+        buf[..data.len()].copy_from_slice(&data);
+        Ok(data.len())
     }
-}
 
-#[derive(Debug, PartialEq)]
-pub struct Frame {
-    data: Vec<u8>,
-}
+    fn try_next(&mut self) -> Result<Option<Frame>> {
+        self.ensure_started()?;
 
-impl<R: Read> Iterator for Decoder<R> {
-    type Item = Frame;
-    fn next(&mut self) -> Option<Self::Item> {
-        if !self.started {
-            self.read_start_frame().ok()?;
-            self.started = true;
-        }
-
-        let frame_len = self.read_frame_length().ok()?;
+        let frame_len = self.read_frame_length()?;
         if frame_len == 0 {
             // Control frame.
-            let frame = self.read_control_frame().ok()?;
-            if frame.control_type == CONTROL_STOP {
-                // TODO(nesv): Write a CONTROL_FINISH frame.
-                return None;
+            let frame = self.read_control_frame()?;
+            if frame.control_type != CONTROL_STOP {
+                return Err(FramestreamError::new(
+                    ErrorKind::UnexpectedControlType {
+                        expected: CONTROL_STOP,
+                        got: frame.control_type,
+                    },
+                    self.pos,
+                ));
             }
+            self.finish_if_bidirectional()?;
+            return Ok(None);
         }
 
-        let mut buf = Vec::with_capacity(frame_len);
-        buf.resize(frame_len, 0);
-        match self.read_n(frame_len, &mut buf[..]) {
-            Ok(_) => Some(Frame { data: buf }),
-            Err(_) => None,
-        }
+        let buf = self.read_data_frame(frame_len)?;
+        Ok(Some(Frame { data: buf }))
     }
 }
 
-#[cfg(test)]
-#[test]
-fn iter() {
-    let input = std::io::Cursor::new([
-        0, 0, 0, 0, 0, 0, 0, 29, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 17, 116, 101, 115, 116, 45, 99,
-        111, 110, 116, 101, 110, 116, 45, 116, 121, 112, 101, 0, 0, 0, 12, 116, 101, 115, 116, 45,
-        99, 111, 110, 116, 101, 110, 116, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 3,
-    ]);
-    let mut decoder = Decoder::new(input);
-    let want = "test-content".as_bytes().to_vec();
-    assert_eq!(decoder.next(), Some(Frame { data: want }));
-    assert_eq!(decoder.next(), None);
+struct ControlFrame {
+    control_type: u32,
+    content_types: Option<Vec<String>>,
 }
 
-#[test]
-fn read() {
-    let input = std::io::Cursor::new([
-        0, 0, 0, 0, 0, 0, 0, 29, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 17, 116, 101, 115, 116, 45, 99,
-        111, 110, 116, 101, 110, 116, 45, 116, 121, 112, 101, 0, 0, 0, 12, 116, 101, 115, 116, 45,
-        99, 111, 110, 116, 101, 110, 116, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 3,
-    ]);
-    let mut decoder = Decoder::new(input);
-    let mut buf = [0; 1 << 10];
-    let n = decoder.read(&mut buf[..]).unwrap();
-    let got = std::str::from_utf8(&buf[..n]).unwrap();
-    assert_eq!(got, "test-content");
+#[cfg(not(feature = "no_std"))]
+impl<R: ByteSource> std::io::Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.read_data_frame_inner(buf) {
+            Ok(n) => Ok(n),
+            Err(e) if e.is_eof() => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
 }
 
-#[test]
-fn read_start_frame() {
-    let input = std::io::Cursor::new([
-        0, 0, 0, 0, 0, 0, 0, 29, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 17, 116, 101, 115, 116, 45, 99,
-        111, 110, 116, 101, 110, 116, 45, 116, 121, 112, 101, 0, 0, 0, 12, 116, 101, 115, 116, 45,
-        99, 111, 110, 116, 101, 110, 116, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 3,
-    ]);
-    let mut decoder = Decoder::new(input);
-    decoder.read_start_frame().unwrap();
+#[derive(Debug, PartialEq)]
+pub struct Frame {
+    data: Vec<u8>,
 }
 
-#[test]
-fn read_control_frame() {
-    let input = std::io::Cursor::new([
-        0, 0, 0, 29, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 17, 116, 101, 115, 116, 45, 99, 111, 110,
-        116, 101, 110, 116, 45, 116, 121, 112, 101,
-    ]);
-    let mut decoder = Decoder::new(input);
-    let control_frame = decoder.read_control_frame().unwrap();
-    assert_eq!(
-        control_frame.content_types,
-        Some(vec!["test-content-type".to_string()])
-    );
+impl Frame {
+    /// The raw bytes of this frame's payload.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
 }
 
-#[test]
-fn read_control_field() {
-    let input = std::io::Cursor::new([
-        0, 0, 0, 1, 0, 0, 0, 17, 116, 101, 115, 116, 45, 99, 111, 110, 116, 101, 110, 116, 45, 116,
-        121, 112, 101,
-    ]);
-    let mut decoder = Decoder::new(input);
-    let (ctype, bytes_read) = decoder.read_control_field(29).unwrap();
-    assert_eq!(&ctype, "test-content-type");
-    assert_eq!(bytes_read, 25);
+impl<R: ByteSource> Iterator for Decoder<R> {
+    type Item = Result<Frame>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.try_next() {
+            Ok(Some(frame)) => Some(Ok(frame)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "no_std")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iter() {
+        let input = std::io::Cursor::new([
+            0, 0, 0, 0, 0, 0, 0, 29, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 17, 116, 101, 115, 116, 45, 99,
+            111, 110, 116, 101, 110, 116, 45, 116, 121, 112, 101, 0, 0, 0, 12, 116, 101, 115, 116, 45,
+            99, 111, 110, 116, 101, 110, 116, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 3,
+        ]);
+        let mut decoder = Decoder::new(input);
+        let want = "test-content".as_bytes().to_vec();
+        assert_eq!(decoder.next().unwrap().unwrap(), Frame { data: want });
+        assert!(decoder.next().is_none());
+    }
+
+    #[test]
+    fn read() {
+        let input = std::io::Cursor::new([
+            0, 0, 0, 0, 0, 0, 0, 29, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 17, 116, 101, 115, 116, 45, 99,
+            111, 110, 116, 101, 110, 116, 45, 116, 121, 112, 101, 0, 0, 0, 12, 116, 101, 115, 116, 45,
+            99, 111, 110, 116, 101, 110, 116, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 3,
+        ]);
+        let mut decoder = Decoder::new(input);
+        let mut buf = [0; 1 << 10];
+        let n = std::io::Read::read(&mut decoder, &mut buf[..]).unwrap();
+        let got = std::str::from_utf8(&buf[..n]).unwrap();
+        assert_eq!(got, "test-content");
+    }
+
+    #[test]
+    fn read_start_frame() {
+        let input = std::io::Cursor::new([
+            0, 0, 0, 0, 0, 0, 0, 29, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 17, 116, 101, 115, 116, 45, 99,
+            111, 110, 116, 101, 110, 116, 45, 116, 121, 112, 101, 0, 0, 0, 12, 116, 101, 115, 116, 45,
+            99, 111, 110, 116, 101, 110, 116, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 3,
+        ]);
+        let mut decoder = Decoder::new(input);
+        decoder.read_start_frame().unwrap();
+        assert_eq!(decoder.negotiated_content_type(), Some("test-content-type"));
+    }
+
+    #[test]
+    fn read_control_frame() {
+        let input = std::io::Cursor::new([
+            0, 0, 0, 29, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 17, 116, 101, 115, 116, 45, 99, 111, 110,
+            116, 101, 110, 116, 45, 116, 121, 112, 101,
+        ]);
+        let mut decoder = Decoder::new(input);
+        let control_frame = decoder.read_control_frame().unwrap();
+        assert_eq!(
+            control_frame.content_types,
+            Some(vec!["test-content-type".to_string()])
+        );
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn bidirectional_handshake() {
+        // CONTROL_READY offering "test-content-type", followed by CONTROL_START
+        // and a single data frame.
+        let input = std::io::Cursor::new([
+            0, 0, 0, 0, 0, 0, 0, 29, 0, 0, 0, 4, 0, 0, 0, 1, 0, 0, 0, 17, 116, 101, 115, 116, 45, 99,
+            111, 110, 116, 101, 110, 116, 45, 116, 121, 112, 101, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 2,
+            0, 0, 0, 4, 116, 101, 115, 116,
+        ]);
+        let written = SharedBuf::default();
+        let mut decoder = Decoder::new(input);
+        decoder.bidirectional(written.clone());
+
+        let want = "test".as_bytes().to_vec();
+        assert_eq!(decoder.next().unwrap().unwrap(), Frame { data: want });
+
+        // The decoder should have written a CONTROL_ACCEPT naming the offered
+        // content type.
+        assert_eq!(
+            written.0.borrow().as_slice(),
+            &[
+                0, 0, 0, 0, 0, 0, 0, 29, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 17, 116, 101, 115, 116, 45,
+                99, 111, 110, 116, 101, 110, 116, 45, 116, 121, 112, 101
+            ][..]
+        );
+    }
+
+    #[test]
+    fn max_frame_length_rejects_oversized_frame() {
+        // A data frame declaring a length of 0x7fffffff bytes, but with no
+        // actual payload following it.
+        let input = std::io::Cursor::new([
+            0, 0, 0, 0, 0, 0, 0, 29, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 17, 116, 101, 115, 116, 45, 99,
+            111, 110, 116, 101, 110, 116, 45, 116, 121, 112, 101, 0x7f, 0xff, 0xff, 0xff,
+        ]);
+        let mut decoder = Decoder::new(input);
+        decoder.max_frame_length(1024);
+        let err = decoder.next().unwrap().unwrap_err();
+        assert!(!err.is_eof());
+        assert!(matches!(err.kind(), ErrorKind::FrameTooLarge { .. }));
+    }
+
+    #[test]
+    fn truncated_frame_is_not_reported_as_eof() {
+        // A CONTROL_START frame followed by a data frame that declares 4 bytes
+        // of payload but only has 2.
+        let input = std::io::Cursor::new([
+            0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 2, 0, 0, 0, 4, 116, 101,
+        ]);
+        let mut decoder = Decoder::new(input);
+        let err = decoder.next().unwrap().unwrap_err();
+        assert!(!err.is_eof());
+        assert_eq!(err.kind(), &ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn unexpected_control_type_after_start_is_reported_as_error() {
+        // A CONTROL_START frame followed by a stray CONTROL_READY instead of a
+        // data frame or CONTROL_STOP.
+        let input = std::io::Cursor::new([
+            0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 4,
+        ]);
+        let mut decoder = Decoder::new(input);
+        let err = decoder.next().unwrap().unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &ErrorKind::UnexpectedControlType {
+                expected: CONTROL_STOP,
+                got: CONTROL_READY,
+            }
+        );
+    }
+
+    #[test]
+    fn clean_eof_without_stop_is_reported_as_eof() {
+        // A lone CONTROL_START frame with nothing following it at all.
+        let input = std::io::Cursor::new([0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 2]);
+        let mut decoder = Decoder::new(input);
+        let err = decoder.next().unwrap().unwrap_err();
+        assert!(err.is_eof());
+    }
+
+    #[test]
+    fn read_control_field() {
+        let input = std::io::Cursor::new([
+            0, 0, 0, 1, 0, 0, 0, 17, 116, 101, 115, 116, 45, 99, 111, 110, 116, 101, 110, 116, 45, 116,
+            121, 112, 101,
+        ]);
+        let mut decoder = Decoder::new(input);
+        let (ctype, bytes_read) = decoder.read_control_field(29).unwrap();
+        assert_eq!(&ctype, "test-content-type");
+        assert_eq!(bytes_read, 25);
+    }
+
+    #[test]
+    fn codec_round_trip() {
+        let mut written = Vec::new();
+        let mut enc = crate::encoder::EncoderWriter::new(&mut written);
+        enc.with_codec(crate::codec::Codec::Gzip);
+        enc.write_start(None).unwrap();
+        enc.write_frame(b"test-content").unwrap();
+        enc.write_stop().unwrap();
+
+        let mut decoder = Decoder::new(std::io::Cursor::new(written));
+        decoder.with_codec(crate::codec::Codec::Gzip);
+        assert_eq!(
+            decoder.next().unwrap().unwrap(),
+            Frame {
+                data: b"test-content".to_vec()
+            }
+        );
+        assert!(decoder.next().is_none());
+    }
 }
@@ -0,0 +1,103 @@
+use crate::codec::Codec;
+use crate::constants::{
+    CONTROL_ACCEPT, CONTROL_FIELD_CONTENT_TYPE, CONTROL_FINISH, CONTROL_READY, CONTROL_START,
+    CONTROL_STOP,
+};
+use byteorder::{BigEndian, WriteBytesExt};
+use std::io::{Result, Write};
+
+/// Writes Frame Streams data frames, and the control frames that bound a
+/// stream, to an underlying writer.
+#[derive(Clone, Debug)]
+pub struct EncoderWriter<W: Write> {
+    writer: W,
+    codec: Codec,
+}
+
+impl<W: Write> EncoderWriter<W> {
+    /// Instantiate a new EncoderWriter that writes to `sink`.
+    pub fn new(sink: W) -> Self {
+        Self {
+            writer: sink,
+            codec: Codec::default(),
+        }
+    }
+
+    /// Transparently compress each data frame's payload under `codec`
+    /// before writing it. Defaults to [`Codec::Identity`].
+    ///
+    /// The peer's [`Decoder`](crate::Decoder) must be configured with a
+    /// matching codec via [`Decoder::with_codec`](crate::Decoder::with_codec)
+    /// to read the resulting stream back.
+    pub fn with_codec(&mut self, codec: Codec) {
+        self.codec = codec;
+    }
+
+    /// Write a single data frame containing `data`, compressed under this
+    /// writer's configured codec.
+    pub fn write_frame(&mut self, data: &[u8]) -> Result<()> {
+        let data = self.codec.compress(data)?;
+        self.writer.write_u32::<BigEndian>(data.len() as u32)?;
+        self.writer.write_all(&data)?;
+        Ok(())
+    }
+
+    /// Write a `CONTROL_START` frame, optionally declaring the content type
+    /// of the data frames that will follow.
+    pub fn write_start(&mut self, content_type: Option<&str>) -> Result<()> {
+        self.write_control_frame(CONTROL_START, content_type.into_iter())
+    }
+
+    /// Write a `CONTROL_STOP` frame.
+    pub fn write_stop(&mut self) -> Result<()> {
+        self.write_control_frame(CONTROL_STOP, std::iter::empty())
+    }
+
+    /// Write a `CONTROL_READY` frame, offering the content types this
+    /// writer is able to produce.
+    pub fn write_ready<'a, I>(&mut self, content_types: I) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.write_control_frame(CONTROL_READY, content_types.into_iter())
+    }
+
+    /// Write a `CONTROL_ACCEPT` frame, naming the content types a peer's
+    /// `CONTROL_READY` offer was accepted for.
+    pub fn write_accept<'a, I>(&mut self, content_types: I) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.write_control_frame(CONTROL_ACCEPT, content_types.into_iter())
+    }
+
+    /// Write a `CONTROL_FINISH` frame, acknowledging a peer's
+    /// `CONTROL_STOP`.
+    pub fn write_finish(&mut self) -> Result<()> {
+        self.write_control_frame(CONTROL_FINISH, std::iter::empty())
+    }
+
+    /// Write a control frame of the given `control_type`, with a content
+    /// type field for each entry in `content_types`.
+    fn write_control_frame<'a, I>(&mut self, control_type: u32, content_types: I) -> Result<()>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let content_types: Vec<&str> = content_types.collect();
+        let mut frame_len = 4u32; // control_type
+        for ct in &content_types {
+            frame_len += 8 + ct.len() as u32;
+        }
+
+        self.writer.write_u32::<BigEndian>(0)?; // escape frame
+        self.writer.write_u32::<BigEndian>(frame_len)?;
+        self.writer.write_u32::<BigEndian>(control_type)?;
+        for ct in &content_types {
+            self.writer
+                .write_u32::<BigEndian>(CONTROL_FIELD_CONTENT_TYPE)?;
+            self.writer.write_u32::<BigEndian>(ct.len() as u32)?;
+            self.writer.write_all(ct.as_bytes())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use crate::io::SourceError;
+use core::fmt;
+
+/// The specific condition that caused a [`FramestreamError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The underlying reader reached end-of-file exactly on a frame
+    /// boundary. This is a clean end of stream, not a real failure.
+    Eof,
+    /// The underlying reader reached end-of-file in the middle of a frame.
+    UnexpectedEof,
+    /// A control frame declared a length above the maximum allowed.
+    ControlFrameTooLarge { len: usize },
+    /// A control frame declared a length below the minimum of 4 bytes.
+    ControlFrameTooShort { len: usize },
+    /// The 4-byte escape sequence (`0x00000000`) that must precede a
+    /// control frame was missing.
+    MissingEscape { got: u32 },
+    /// A control frame's type did not match what was expected at this point
+    /// in the stream.
+    UnexpectedControlType { expected: u32, got: u32 },
+    /// A control field's type was not `CONTROL_FIELD_CONTENT_TYPE`.
+    BadContentTypeField { got: u32 },
+    /// A control field declared a length that didn't fit within the
+    /// remaining space of its enclosing control frame.
+    ContentTypeFieldTooLarge { len: usize, limit: usize },
+    /// A data frame declared a length over the decoder's configured
+    /// maximum.
+    FrameTooLarge { len: usize, max: usize },
+    /// A data frame's payload, after decompression under the configured
+    /// codec, was larger than the buffer a caller passed to
+    /// [`std::io::Read::read`].
+    BufferTooSmall { frame_len: usize, buf_len: usize },
+    /// An I/O error from the underlying reader, or from the writer of a
+    /// bidirectional decoder, other than EOF.
+    Io,
+    /// A data frame's payload failed to decompress under its configured
+    /// [`Codec`](crate::Codec): a corrupt frame, a truncated block, or a
+    /// checksum mismatch.
+    BadCodecFrame,
+    /// [`SeekableDecoder::seek_to_frame`](crate::SeekableDecoder::seek_to_frame)
+    /// was called with an index past the end of the frames indexed so far.
+    FrameIndexOutOfRange { index: usize, len: usize },
+    /// [`SeekableDecoder::resync`](crate::SeekableDecoder::resync) scanned
+    /// to the end of the stream without finding a valid `CONTROL_START`
+    /// frame.
+    ResyncFailed,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Eof => write!(f, "end of stream"),
+            Self::UnexpectedEof => write!(f, "unexpected end of stream mid-frame"),
+            Self::ControlFrameTooLarge { len } => {
+                write!(f, "control frame too large: len={}", len)
+            }
+            Self::ControlFrameTooShort { len } => {
+                write!(f, "control frame too short: len={}", len)
+            }
+            Self::MissingEscape { got } => {
+                write!(f, "expected escape sequence, got={:x}", got)
+            }
+            Self::UnexpectedControlType { expected, got } => write!(
+                f,
+                "unexpected control type: expected={:x} got={:x}",
+                expected, got
+            ),
+            Self::BadContentTypeField { got } => {
+                write!(f, "expected control field content type, got={:x}", got)
+            }
+            Self::ContentTypeFieldTooLarge { len, limit } => write!(
+                f,
+                "control field contents too large: len={} limit={}",
+                len, limit
+            ),
+            Self::FrameTooLarge { len, max } => {
+                write!(f, "data frame too large: len={} max={}", len, max)
+            }
+            Self::BufferTooSmall { frame_len, buf_len } => write!(
+                f,
+                "data frame too large for buffer: len={} buf_len={}",
+                frame_len, buf_len
+            ),
+            Self::Io => write!(f, "I/O error"),
+            Self::BadCodecFrame => write!(f, "frame failed to decompress"),
+            Self::FrameIndexOutOfRange { index, len } => write!(
+                f,
+                "frame index out of range: index={} len={}",
+                index, len
+            ),
+            Self::ResyncFailed => write!(f, "resync failed: no start frame found"),
+        }
+    }
+}
+
+/// An error encountered while decoding a Frame Streams data stream.
+///
+/// Every variant records the byte offset, relative to the start of the
+/// underlying reader, at which the failure was detected, and
+/// [`FramestreamError::is_eof`] distinguishes a clean end of stream from a
+/// truncated or corrupt one.
+#[derive(Debug)]
+pub struct FramestreamError {
+    kind: ErrorKind,
+    offset: u64,
+    source: Option<SourceError>,
+}
+
+impl FramestreamError {
+    pub(crate) fn new(kind: ErrorKind, offset: u64) -> Self {
+        Self {
+            kind,
+            offset,
+            source: None,
+        }
+    }
+
+    /// Wrap an I/O error encountered at `offset`, distinguishing unexpected
+    /// EOF from other I/O failures.
+    pub(crate) fn io(err: SourceError, offset: u64) -> Self {
+        #[cfg(not(feature = "no_std"))]
+        if err.kind() == std::io::ErrorKind::UnexpectedEof {
+            return Self::new(ErrorKind::UnexpectedEof, offset);
+        }
+        Self {
+            kind: ErrorKind::Io,
+            offset,
+            source: Some(err),
+        }
+    }
+
+    /// The kind of failure this error represents.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// The byte offset, relative to the start of the underlying reader, at
+    /// which this failure was detected.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Whether this error represents a clean end of stream, as opposed to a
+    /// truncated or corrupt frame.
+    pub fn is_eof(&self) -> bool {
+        self.kind == ErrorKind::Eof
+    }
+}
+
+impl fmt::Display for FramestreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at offset {}: {}", self.offset, self.kind)
+    }
+}
+
+impl core::error::Error for FramestreamError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|e| e as &(dyn core::error::Error + 'static))
+    }
+}
+
+#[cfg(not(feature = "no_std"))]
+impl From<FramestreamError> for std::io::Error {
+    fn from(err: FramestreamError) -> Self {
+        std::io::Error::other(err)
+    }
+}
@@ -0,0 +1,46 @@
+//! A minimal byte-source abstraction that lets [`Decoder`](crate::Decoder)
+//! run without `std`.
+//!
+//! With the `no_std` feature disabled (the default), any `std::io::Read`
+//! works as a [`ByteSource`] via the blanket impl below, so existing
+//! callers are unaffected. With `no_std` enabled, `std::io::Read` doesn't
+//! exist, so `ByteSource` becomes the crate's only notion of "a thing
+//! bytes can be read from", and embedded/WASM callers implement it
+//! directly against their I/O primitive.
+
+#[cfg(not(feature = "no_std"))]
+pub type SourceError = std::io::Error;
+
+#[cfg(feature = "no_std")]
+pub type SourceError = NoStdIoError;
+
+/// The error a [`ByteSource`] read fails with, when built with the
+/// `no_std` feature.
+#[cfg(feature = "no_std")]
+#[derive(Debug)]
+pub struct NoStdIoError;
+
+#[cfg(feature = "no_std")]
+impl core::fmt::Display for NoStdIoError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "byte source read failed")
+    }
+}
+
+#[cfg(feature = "no_std")]
+impl core::error::Error for NoStdIoError {}
+
+/// A source of bytes a [`Decoder`](crate::Decoder) can read frames from.
+///
+/// This mirrors `std::io::Read::read`: implementations return the number
+/// of bytes read, or `0` at a clean end of stream.
+pub trait ByteSource {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, SourceError>;
+}
+
+#[cfg(not(feature = "no_std"))]
+impl<T: std::io::Read> ByteSource for T {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, SourceError> {
+        std::io::Read::read(self, buf)
+    }
+}
@@ -1,8 +1,37 @@
+//! A decoder (and, unless the `no_std` feature is enabled, an encoder) for
+//! the [Frame Streams](https://github.com/farsightsec/fstrm) protocol.
+//!
+//! Enabling the `no_std` feature drops everything that depends on
+//! `std::io` — [`EncoderWriter`], [`TypedDecoder`], [`SeekableDecoder`]
+//! and [`Codec`] are unavailable, and
+//! [`Decoder`] reads from any [`ByteSource`] instead of a `std::io::Read`.
+#![cfg_attr(feature = "no_std", no_std)]
+
+#[cfg(feature = "no_std")]
+extern crate alloc;
+
+#[cfg(not(feature = "no_std"))]
+mod codec;
 mod constants;
 mod decoder;
+#[cfg(not(feature = "no_std"))]
 mod encoder;
+mod error;
+mod io;
+#[cfg(not(feature = "no_std"))]
+mod seekable;
+#[cfg(not(feature = "no_std"))]
+mod typed;
 
-pub use crate::{decoder::Decoder, encoder::EncoderWriter};
-
-#[cfg(test)]
-mod tests;
+pub use crate::{
+    decoder::Decoder,
+    error::{ErrorKind, FramestreamError},
+    io::ByteSource,
+};
+#[cfg(not(feature = "no_std"))]
+pub use crate::{
+    codec::Codec,
+    encoder::EncoderWriter,
+    seekable::SeekableDecoder,
+    typed::{DecodeFrame, TypedDecoder},
+};
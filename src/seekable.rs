@@ -0,0 +1,278 @@
+//! Seekable, multi-segment reading of Frame Streams captures.
+//!
+//! A large on-disk capture is often a concatenation of independent
+//! `CONTROL_START`…`CONTROL_STOP` segments, produced by rotating a writer
+//! onto the same file. [`SeekableDecoder`] walks every segment with a
+//! single reader, records the byte offset of each frame as it scans, and
+//! can resynchronize past a corrupt region by scanning for the next
+//! `CONTROL_START`.
+
+use crate::constants::CONTROL_START;
+use crate::decoder::{Decoder, Frame, MAX_CONTROL_FRAME_LENGTH};
+use crate::error::{ErrorKind, FramestreamError};
+use std::io::{Read, Seek, SeekFrom};
+
+/// The result of a fallible `SeekableDecoder` operation.
+type Result<T> = std::result::Result<T, FramestreamError>;
+
+/// A [`Decoder`] over a seekable reader that treats a capture file as a
+/// sequence of independent `CONTROL_START`…`CONTROL_STOP` segments,
+/// reading past each `CONTROL_STOP` into the next segment rather than
+/// terminating, and indexing the byte offset of every frame it yields so
+/// callers can seek back to it.
+pub struct SeekableDecoder<R: Read + Seek> {
+    inner: Decoder<R>,
+    /// The byte offset of each frame yielded so far, in the order it was
+    /// read (the segment's `CONTROL_START` offset, for a segment's first
+    /// frame).
+    frame_index: Vec<u64>,
+    /// Parallel to `frame_index`: whether the frame at that offset is the
+    /// first frame of a segment, i.e. a `CONTROL_START` still needs to be
+    /// read there rather than having already been consumed.
+    segment_starts: Vec<bool>,
+}
+
+impl<R: Read + Seek> SeekableDecoder<R> {
+    /// Instantiate a new SeekableDecoder that can read from the given
+    /// `source`.
+    pub fn new(source: R) -> Self {
+        Self {
+            inner: Decoder::new(source),
+            frame_index: Vec::new(),
+            segment_starts: Vec::new(),
+        }
+    }
+
+    /// Record `ctype` as the inner [`Decoder`]'s content type. A
+    /// `SeekableDecoder` neither performs the bidirectional handshake nor
+    /// decodes typed frames, so frames are not filtered by it: every data
+    /// frame is returned, whatever content type its segment declares.
+    pub fn content_type(&mut self, ctype: &str) {
+        self.inner.content_type(ctype);
+    }
+
+    /// Set the maximum allowed length, in bytes, of a data frame's
+    /// declared length. See [`Decoder::max_frame_length`].
+    pub fn max_frame_length(&mut self, max: usize) {
+        self.inner.max_frame_length(max);
+    }
+
+    /// The byte offset of each frame yielded so far, in the order it was
+    /// read; for the first frame of a segment, this is the offset of the
+    /// segment's `CONTROL_START`. `frame_index()[n]` is the offset
+    /// [`SeekableDecoder::seek_to_frame`] needs to return to the `n`th frame.
+    pub fn frame_index(&self) -> &[u64] {
+        &self.frame_index
+    }
+
+    /// Seek directly to the `n`th frame recorded in
+    /// [`SeekableDecoder::frame_index`], so the next call to
+    /// [`Iterator::next`] re-reads it. Only frames already passed over
+    /// can be seeked to; `n` must be less than `frame_index().len()`.
+    pub fn seek_to_frame(&mut self, n: usize) -> Result<()> {
+        let offset = *self.frame_index.get(n).ok_or_else(|| {
+            FramestreamError::new(
+                ErrorKind::FrameIndexOutOfRange {
+                    index: n,
+                    len: self.frame_index.len(),
+                },
+                self.inner.pos(),
+            )
+        })?;
+        let started = !self.segment_starts[n];
+        self.inner.seek_to(offset, started)
+    }
+
+    /// After a corrupt or misaligned region, scan forward from the
+    /// current position for the next valid `CONTROL_START` frame — a run
+    /// of four zero bytes (the escape sequence) immediately followed by a
+    /// frame length and the `CONTROL_START` control type — the way
+    /// sequence-file-style formats hunt for sync markers. On success, the
+    /// decoder is left positioned to read that `CONTROL_START` next.
+    pub fn resync(&mut self) -> Result<()> {
+        let mut zero_run = 0usize;
+        loop {
+            let before = self.stream_pos()?;
+            let mut byte = [0u8; 1];
+            let n = self
+                .inner
+                .reader_mut()
+                .read(&mut byte)
+                .map_err(|e| FramestreamError::io(e, before))?;
+            if n == 0 {
+                return Err(FramestreamError::new(ErrorKind::ResyncFailed, before));
+            }
+
+            if byte[0] != 0 {
+                zero_run = 0;
+                continue;
+            }
+            zero_run += 1;
+            if zero_run < 4 {
+                continue;
+            }
+
+            let candidate = before + 1 - 4;
+            if self.probe_start_frame(candidate)? {
+                self.inner.seek_to(candidate, false)?;
+                return Ok(());
+            }
+            // Not a real CONTROL_START. `probe_start_frame` already
+            // restored the reader to just past this failed window
+            // (`candidate + 4`), and the last three bytes of that window
+            // are still zero, so slide forward by one instead of
+            // discarding them and rescanning from scratch.
+            zero_run = 3;
+        }
+    }
+
+    /// Peek at the 8 bytes following the candidate escape sequence that
+    /// begins at `offset` (i.e. starting at `offset + 4`) to check whether
+    /// they form a `CONTROL_START` frame header, restoring the reader's
+    /// position before returning.
+    fn probe_start_frame(&mut self, offset: u64) -> Result<bool> {
+        let resume_from = self.stream_pos()?;
+        self.seek_raw(offset + 4)?;
+
+        let mut header = [0u8; 8];
+        let header_ok = self.inner.reader_mut().read_exact(&mut header).is_ok();
+
+        self.seek_raw(resume_from)?;
+
+        if !header_ok {
+            return Ok(false);
+        }
+        let frame_len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        let control_type = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        Ok(control_type == CONTROL_START && (4..=MAX_CONTROL_FRAME_LENGTH as u32).contains(&frame_len))
+    }
+
+    fn stream_pos(&mut self) -> Result<u64> {
+        self.inner
+            .reader_mut()
+            .stream_position()
+            .map_err(|e| FramestreamError::io(e, self.inner.pos()))
+    }
+
+    fn seek_raw(&mut self, pos: u64) -> Result<()> {
+        self.inner
+            .reader_mut()
+            .seek(SeekFrom::Start(pos))
+            .map(|_| ())
+            .map_err(|e| FramestreamError::io(e, pos))
+    }
+}
+
+impl<R: Read + Seek> Iterator for SeekableDecoder<R> {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let segment_start = !self.inner.is_started();
+        let offset = self.inner.pos();
+        match self.inner.next() {
+            Some(Ok(frame)) => {
+                self.frame_index.push(offset);
+                self.segment_starts.push(segment_start);
+                Some(Ok(frame))
+            }
+            Some(Err(e)) => Some(Err(e)),
+            None => {
+                // CONTROL_STOP ended this segment. Rather than terminating,
+                // look for a subsequent CONTROL_START so a single reader
+                // can walk every segment in a rotated capture file,
+                // passing over any segment that holds no data frames.
+                loop {
+                    self.inner.reset_started();
+                    let offset = self.inner.pos();
+                    match self.inner.next() {
+                        Some(Ok(frame)) => {
+                            self.frame_index.push(offset);
+                            self.segment_starts.push(true);
+                            return Some(Ok(frame));
+                        }
+                        Some(Err(e)) if e.is_eof() => return None,
+                        Some(Err(e)) => return Some(Err(e)),
+                        None => continue,
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn segment(content: &[u8]) -> Vec<u8> {
+    let mut written = Vec::new();
+    let mut enc = crate::encoder::EncoderWriter::new(&mut written);
+    enc.write_start(None).unwrap();
+    enc.write_frame(content).unwrap();
+    enc.write_stop().unwrap();
+    written
+}
+
+#[test]
+fn walks_concatenated_segments() {
+    let mut input = segment(b"first");
+    input.extend(segment(b"second"));
+
+    let mut decoder = SeekableDecoder::new(std::io::Cursor::new(input));
+    assert_eq!(decoder.next().unwrap().unwrap().data(), b"first");
+    assert_eq!(decoder.next().unwrap().unwrap().data(), b"second");
+    assert!(decoder.next().is_none());
+}
+
+#[test]
+fn seek_to_frame_rereads_a_past_frame() {
+    let mut input = segment(b"first");
+    input.extend(segment(b"second"));
+
+    let mut decoder = SeekableDecoder::new(std::io::Cursor::new(input));
+    assert_eq!(decoder.next().unwrap().unwrap().data(), b"first");
+    assert_eq!(decoder.next().unwrap().unwrap().data(), b"second");
+
+    decoder.seek_to_frame(0).unwrap();
+    assert_eq!(decoder.next().unwrap().unwrap().data(), b"first");
+}
+
+#[test]
+fn seek_to_frame_out_of_range_is_an_error() {
+    let input = segment(b"first");
+    let mut decoder = SeekableDecoder::new(std::io::Cursor::new(input));
+    let err = decoder.seek_to_frame(0).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        ErrorKind::FrameIndexOutOfRange { index: 0, len: 0 }
+    ));
+}
+
+#[test]
+fn resync_finds_the_next_start_after_garbage() {
+    let mut input = vec![0xffu8; 13];
+    input.extend(segment(b"recovered"));
+
+    let mut decoder = SeekableDecoder::new(std::io::Cursor::new(input));
+    decoder.resync().unwrap();
+    assert_eq!(decoder.next().unwrap().unwrap().data(), b"recovered");
+}
+
+#[test]
+fn resync_fails_when_no_start_frame_follows() {
+    let input = vec![0xffu8; 32];
+    let mut decoder = SeekableDecoder::new(std::io::Cursor::new(input));
+    let err = decoder.resync().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::ResyncFailed);
+}
+
+#[test]
+fn resync_skips_past_a_false_positive_escape_sequence() {
+    // Four zero bytes that look like an escape sequence but are followed
+    // by a declared control-frame length of 2, which is invalid (too
+    // short) and must be rejected, rather than wrongly accepted or
+    // causing the scan to loop without making progress.
+    let mut input = vec![0, 0, 0, 0, 0, 0, 0, 2, 0xff, 0xff, 0xff, 0xff];
+    input.extend(segment(b"recovered"));
+
+    let mut decoder = SeekableDecoder::new(std::io::Cursor::new(input));
+    decoder.resync().unwrap();
+    assert_eq!(decoder.next().unwrap().unwrap().data(), b"recovered");
+}